@@ -0,0 +1,58 @@
+use std::io::{self, Write};
+
+// writes a history as an ASCII PBM (P1) image, one row per generation
+pub fn write_pbm(history: &[Vec<u8>], writer: &mut impl Write) -> io::Result<()> {
+    let height = history.len();
+    let width = history.first().map_or(0, |row| row.len());
+
+    writeln!(writer, "P1")?;
+    writeln!(writer, "{} {}", width, height)?;
+    for row in history {
+        let line: Vec<String> = row.iter().map(|cell| cell.to_string()).collect();
+        writeln!(writer, "{}", line.join(" "))?;
+    }
+    Ok(())
+}
+
+// writes a history as a grayscale PGM (P2) image, one row per generation
+pub fn write_pgm(history: &[Vec<u8>], writer: &mut impl Write) -> io::Result<()> {
+    let height = history.len();
+    let width = history.first().map_or(0, |row| row.len());
+
+    writeln!(writer, "P2")?;
+    writeln!(writer, "{} {}", width, height)?;
+    writeln!(writer, "255")?;
+    for row in history {
+        let line: Vec<String> = row
+            .iter()
+            .map(|&cell| if cell != 0 { "0" } else { "255" }.to_string())
+            .collect();
+        writeln!(writer, "{}", line.join(" "))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_pbm_header_and_rows() {
+        let history = vec![vec![0, 1, 0], vec![1, 1, 1]];
+        let mut buffer = Vec::new();
+        write_pbm(&history, &mut buffer).unwrap();
+
+        let output = String::from_utf8(buffer).unwrap();
+        assert_eq!(output, "P1\n3 2\n0 1 0\n1 1 1\n");
+    }
+
+    #[test]
+    fn writes_pgm_header_and_rows() {
+        let history = vec![vec![0, 1, 0], vec![1, 1, 1]];
+        let mut buffer = Vec::new();
+        write_pgm(&history, &mut buffer).unwrap();
+
+        let output = String::from_utf8(buffer).unwrap();
+        assert_eq!(output, "P2\n3 2\n255\n255 0 255\n0 0 0\n");
+    }
+}
@@ -0,0 +1,154 @@
+// k-color, radius-r automaton; non-totalistic rules are indexed by the base-k
+// neighborhood pattern, totalistic rules by the neighborhood sum. `fields`
+// uses the same bit-position/array-index convention as `Automaton::fields`.
+// `Automaton` is the `colors == 2, radius == 1`, non-totalistic special case.
+pub struct GeneralizedAutomaton {
+    pub fields: Vec<u8>,
+    pub colors: u8,
+    pub radius: usize,
+    pub rule: Vec<u8>,
+    pub totalistic: bool,
+    pub periodic_boundary: bool,
+}
+
+impl GeneralizedAutomaton {
+    pub fn new(
+        colors: u8,
+        radius: usize,
+        rule: Vec<u8>,
+        totalistic: bool,
+        fields: Vec<u8>,
+        periodic_boundary: bool,
+    ) -> Self {
+        let neighborhood_size = 2 * radius + 1;
+        let expected_rule_len = if totalistic {
+            (colors as usize - 1) * neighborhood_size + 1
+        } else {
+            (colors as usize).pow(neighborhood_size as u32)
+        };
+        assert_eq!(
+            rule.len(),
+            expected_rule_len,
+            "rule table has {} entries, but colors={colors}, radius={radius}, totalistic={totalistic} expects {expected_rule_len}",
+            rule.len(),
+        );
+
+        GeneralizedAutomaton {
+            fields,
+            colors,
+            radius,
+            rule,
+            totalistic,
+            periodic_boundary,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn update(&mut self) {
+        let width = self.width();
+        let mut new_fields = vec![0u8; width];
+        for position in 0..width {
+            let pattern = self.pattern_index(position);
+            new_fields[width - 1 - position] = self.rule[pattern];
+        }
+        self.fields = new_fields;
+    }
+
+    fn digit_at(&self, position: isize) -> u8 {
+        let width = self.width() as isize;
+        if position >= 0 && position < width {
+            return self.fields[width as usize - 1 - position as usize];
+        }
+        if !self.periodic_boundary {
+            return 0;
+        }
+        let wrapped = position.rem_euclid(width) as usize;
+        self.fields[width as usize - 1 - wrapped]
+    }
+
+    fn pattern_index(&self, position: usize) -> usize {
+        let radius = self.radius as isize;
+        let neighborhood = (-radius..=radius)
+            .rev()
+            .map(|offset| self.digit_at(position as isize + offset));
+
+        if self.totalistic {
+            neighborhood.map(|digit| digit as usize).sum()
+        } else {
+            neighborhood.fold(0usize, |acc, digit| {
+                acc * self.colors as usize + digit as usize
+            })
+        }
+    }
+
+    pub fn to_list(&self) -> Vec<u8> {
+        self.fields.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::Automaton;
+
+    fn to_vec(number: u128) -> Vec<u8> {
+        (0..128).map(|i| ((number >> (127 - i)) as u8) & 1).collect()
+    }
+
+    fn elementary_rule_table(rule: u8) -> Vec<u8> {
+        (0..8u8).map(|pattern| (rule >> pattern) & 1).collect()
+    }
+
+    #[test]
+    fn matches_elementary_automaton_for_rule_30() {
+        let mut elementary = Automaton::new(30, to_vec(0b101), false);
+        let mut generalized = GeneralizedAutomaton::new(
+            2,
+            1,
+            elementary_rule_table(30),
+            false,
+            to_vec(0b101),
+            false,
+        );
+
+        for _ in 0..5 {
+            elementary.update();
+            generalized.update();
+            assert_eq!(generalized.fields, elementary.fields);
+        }
+    }
+
+    #[test]
+    fn matches_elementary_automaton_under_periodic_boundary() {
+        let mut elementary = Automaton::new(0b10000, to_vec(0b1), true);
+        let mut generalized = GeneralizedAutomaton::new(
+            2,
+            1,
+            elementary_rule_table(0b10000),
+            false,
+            to_vec(0b1),
+            true,
+        );
+
+        for _ in 0..5 {
+            elementary.update();
+            generalized.update();
+            assert_eq!(generalized.fields, elementary.fields);
+        }
+    }
+
+    #[test]
+    fn totalistic_rule_collapses_by_neighborhood_sum() {
+        // k=2, r=1 totalistic rule table has 4 entries (sums 0..=3). Make
+        // every cell with at least one live neighbor-or-self turn on.
+        let rule = vec![0, 1, 1, 1];
+        let mut automaton =
+            GeneralizedAutomaton::new(2, 1, rule, true, vec![0, 1, 0, 0, 0], false);
+
+        automaton.update();
+        assert_eq!(automaton.fields, vec![1, 1, 1, 0, 0]);
+    }
+}
@@ -1,9 +1,19 @@
 use std::vec;
 
+mod export;
+pub use export::{write_pbm, write_pgm};
+
+mod generalized;
+pub use generalized::GeneralizedAutomaton;
+
 pub struct Automaton {
     pub fields: Vec<u8>,
     pub rule: u8,
     pub periodic_boundary: bool,
+    /// The generation before `fields`, used only by `update_reversible` and
+    /// `step_back`. Starts out as an all-zero row the same width as `fields`;
+    /// callers resizing `fields` directly must resize `previous` to match.
+    pub previous: Vec<u8>,
 }
 
 fn apply_rule(pattern: u8, rule: u8) -> u8 {
@@ -14,27 +24,66 @@ fn find_nth_bit(number: u128, n: usize) -> u8 {
     (number >> n) as u8 & 1
 }
 
+fn word_mask(width: usize) -> u128 {
+    if width >= 128 {
+        u128::MAX
+    } else {
+        (1u128 << width) - 1
+    }
+}
+
+fn word_to_fields(word: u128, width: usize) -> Vec<u8> {
+    (0..width).map(|i| find_nth_bit(word, width - 1 - i)).collect()
+}
+
 impl Automaton {
     pub fn new(rule: u8, initial: Vec<u8>, periodic_boundary: bool) -> Self {
+        let previous = vec![0; initial.len()];
         Automaton {
             fields: initial,
             rule,
             periodic_boundary,
+            previous,
         }
     }
 
+    pub fn width(&self) -> usize {
+        self.fields.len()
+    }
+
     pub fn update(&mut self) {
-        let mut new_fields: Vec<u8> = vec![0; 128];
-        for i in 0..128 {
+        let width = self.width();
+        let mut new_fields: Vec<u8> = vec![0; width];
+        for i in 0..width {
             let pattern = self.detect_pattern(i);
             let new_bit = apply_rule(pattern, self.rule);
-            new_fields[127 - i as usize] = new_bit;
+            new_fields[width - 1 - i] = new_bit;
         }
         self.fields = new_fields;
     }
 
-    fn detect_pattern(&self, center_index: u8) -> u8 {
+    // second-order evolution: XOR the rule output against `previous`, then rotate previous <- fields <- new
+    pub fn update_reversible(&mut self) {
+        let width = self.width();
+        let mut new_fields = vec![0u8; width];
+        for i in 0..width {
+            let pattern = self.detect_pattern(i);
+            let rule_output = apply_rule(pattern, self.rule);
+            new_fields[width - 1 - i] = rule_output ^ self.previous[width - 1 - i];
+        }
+        self.previous = std::mem::replace(&mut self.fields, new_fields);
+    }
+
+    // swap fields/previous so update_reversible steps backwards, then swap back
+    pub fn step_back(&mut self) {
+        std::mem::swap(&mut self.fields, &mut self.previous);
+        self.update_reversible();
+        std::mem::swap(&mut self.fields, &mut self.previous);
+    }
+
+    fn detect_pattern(&self, center_index: usize) -> u8 {
         // digits are stored from end to start, so if center index is 0 we should get the last item. If fixed boundary is set to true, then right of the 0-index should be start of the vector
+        let width = self.width();
         let right = if center_index == 0 {
             if self.periodic_boundary {
                 self.fields[0]
@@ -42,17 +91,17 @@ impl Automaton {
                 0
             }
         } else {
-            self.fields[127 - center_index as usize + 1]
+            self.fields[width - center_index]
         };
-        let center = self.fields[127 - center_index as usize];
-        let left = if center_index == 127 {
+        let center = self.fields[width - 1 - center_index];
+        let left = if center_index == width - 1 {
             if self.periodic_boundary {
-                self.fields[127]
+                self.fields[width - 1]
             } else {
                 0
             }
         } else {
-            self.fields[127 - center_index as usize - 1]
+            self.fields[width - 2 - center_index]
         };
         (left << 2) | (center << 1) | right
     }
@@ -60,6 +109,77 @@ impl Automaton {
     pub fn to_list(&self) -> Vec<u8> {
         self.fields.clone()
     }
+
+    /// Runs the automaton for `generations` steps, recording every row along
+    /// the way (including the initial one), so the full spacetime diagram
+    /// can be inspected or exported afterwards.
+    pub fn run(&mut self, generations: usize) -> Vec<Vec<u8>> {
+        let mut history = Vec::with_capacity(generations + 1);
+        history.push(self.to_list());
+        for _ in 0..generations {
+            self.update();
+            history.push(self.to_list());
+        }
+        history
+    }
+
+    /// Builds an automaton whose initial state is given by the bits of `word`,
+    /// read the same way `to_u128`/`detect_pattern` do: bit `width - 1 - i` of
+    /// `word` becomes `fields[i]`. `width` must not exceed 128.
+    pub fn from_u128(rule: u8, word: u128, width: usize, periodic_boundary: bool) -> Self {
+        assert!(width <= 128, "from_u128 only supports widths up to 128");
+        Automaton::new(rule, word_to_fields(word, width), periodic_boundary)
+    }
+
+    /// Packs `fields` into a single `u128`, the inverse of `from_u128`.
+    pub fn to_u128(&self) -> u128 {
+        let width = self.width();
+        assert!(width <= 128, "to_u128 only supports widths up to 128");
+        self.fields
+            .iter()
+            .enumerate()
+            .filter(|&(_, &bit)| bit != 0)
+            .fold(0u128, |word, (i, _)| word | (1 << (width - 1 - i)))
+    }
+
+    /// Equivalent to `update`, but computes the next generation with a constant
+    /// number of word operations (one match mask per neighborhood pattern)
+    /// instead of a pattern lookup per cell. Packing `fields` into a `u128` and
+    /// unpacking the result still take one pass over the row each. Only works
+    /// for widths up to 128, since the row is packed into a single `u128`.
+    pub fn update_packed(&mut self) {
+        let width = self.width();
+        assert!(width <= 128, "update_packed only supports widths up to 128");
+        if width == 0 {
+            return;
+        }
+        let mask = word_mask(width);
+        let center = self.to_u128();
+
+        let (left, right) = if self.periodic_boundary {
+            let left = (center >> 1) | ((center & 1) << (width - 1));
+            let right = ((center << 1) | (center >> (width - 1))) & mask;
+            (left & mask, right)
+        } else {
+            (center >> 1, (center << 1) & mask)
+        };
+
+        let mut next = 0u128;
+        for pattern in 0u8..8 {
+            let p2 = (pattern >> 2) & 1 == 1;
+            let p1 = (pattern >> 1) & 1 == 1;
+            let p0 = pattern & 1 == 1;
+            let matches = (if p2 { left } else { !left })
+                & (if p1 { center } else { !center })
+                & (if p0 { right } else { !right });
+            if apply_rule(pattern, self.rule) == 1 {
+                next |= matches;
+            }
+        }
+        next &= mask;
+
+        self.fields = word_to_fields(next, width);
+    }
 }
 
 #[cfg(test)]
@@ -100,7 +220,7 @@ mod tests {
         assert_eq!(automaton.detect_pattern(2), 0b010);
         assert_eq!(automaton.detect_pattern(3), 0b001);
         assert_eq!(automaton.detect_pattern(4), 0b000);
-        assert_eq!(automaton.detect_pattern(127), 0b000);
+        assert_eq!(automaton.detect_pattern(automaton.width() - 1), 0b000);
     }
 
     #[test]
@@ -118,12 +238,20 @@ mod tests {
         println!("{:?}", to_vec(0b1));
         assert_eq!(automaton.detect_pattern(0), 0b010);
         assert_eq!(automaton.detect_pattern(1), 0b001);
-        assert_eq!(automaton.detect_pattern(127), 0b100);
+        assert_eq!(automaton.detect_pattern(automaton.width() - 1), 0b100);
 
         let automaton = Automaton::new(30, to_vec(u128::MAX), true);
         assert_eq!(automaton.detect_pattern(0), 0b111);
         assert_eq!(automaton.detect_pattern(1), 0b111);
-        assert_eq!(automaton.detect_pattern(127), 0b111);
+        assert_eq!(automaton.detect_pattern(automaton.width() - 1), 0b111);
+    }
+
+    #[test]
+    fn update_respects_arbitrary_width() {
+        let mut automaton = Automaton::new(30, vec![0, 1, 0], false);
+        automaton.update();
+        assert_eq!(automaton.fields, vec![1, 1, 1]);
+        assert_eq!(automaton.width(), 3);
     }
 
     #[test]
@@ -255,4 +383,102 @@ mod tests {
             to_vec(0b10000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000)
         );
     }
+
+    #[test]
+    fn test_from_u128_round_trips_through_to_u128() {
+        let automaton = Automaton::new(30, to_vec(0b1101), false);
+        assert_eq!(automaton.to_u128(), 0b1101);
+
+        let rebuilt = Automaton::from_u128(30, 0b1101, 128, false);
+        assert_eq!(rebuilt.fields, automaton.fields);
+    }
+
+    #[test]
+    fn update_packed_matches_update_for_rule_30() {
+        let mut cell_by_cell = Automaton::new(30, to_vec(0b101), false);
+        let mut packed = Automaton::new(30, to_vec(0b101), false);
+
+        for _ in 0..5 {
+            cell_by_cell.update();
+            packed.update_packed();
+            assert_eq!(packed.fields, cell_by_cell.fields);
+        }
+    }
+
+    #[test]
+    fn update_packed_matches_update_for_periodic_boundary() {
+        let mut cell_by_cell = Automaton::new(0b10000, to_vec(0b1), true);
+        let mut packed = Automaton::new(0b10000, to_vec(0b1), true);
+
+        for _ in 0..5 {
+            cell_by_cell.update();
+            packed.update_packed();
+            assert_eq!(packed.fields, cell_by_cell.fields);
+        }
+    }
+
+    #[test]
+    fn update_packed_matches_update_for_small_width() {
+        let mut cell_by_cell = Automaton::new(30, vec![0, 1, 0, 1, 0], true);
+        let mut packed = Automaton::new(30, vec![0, 1, 0, 1, 0], true);
+
+        for _ in 0..5 {
+            cell_by_cell.update();
+            packed.update_packed();
+            assert_eq!(packed.fields, cell_by_cell.fields);
+        }
+    }
+
+    #[test]
+    fn run_records_initial_row_and_every_generation() {
+        let mut automaton = Automaton::new(30, vec![0, 1, 0], false);
+        let history = automaton.run(2);
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0], vec![0, 1, 0]);
+        assert_eq!(history.last().unwrap(), &automaton.to_list());
+    }
+
+    #[test]
+    fn run_history_can_be_exported_to_pbm_file() {
+        let mut automaton = Automaton::new(30, vec![0, 0, 1, 0, 0], false);
+        let history = automaton.run(4);
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open("test_output.txt")
+            .unwrap();
+        write_pbm(&history, &mut file).unwrap();
+
+        let contents = std::fs::read_to_string("test_output.txt").unwrap();
+        assert!(contents.starts_with("P1\n5 5\n"));
+    }
+
+    #[test]
+    fn step_back_undoes_update_reversible_for_rule_30() {
+        let mut automaton = Automaton::new(30, to_vec(0b101), false);
+        let original_fields = automaton.fields.clone();
+        let original_previous = automaton.previous.clone();
+
+        automaton.update_reversible();
+        automaton.step_back();
+
+        assert_eq!(automaton.fields, original_fields);
+        assert_eq!(automaton.previous, original_previous);
+    }
+
+    #[test]
+    fn step_back_undoes_update_reversible_under_periodic_boundary() {
+        let mut automaton = Automaton::new(30, to_vec(0b101), true);
+        let original_fields = automaton.fields.clone();
+        let original_previous = automaton.previous.clone();
+
+        automaton.update_reversible();
+        automaton.step_back();
+
+        assert_eq!(automaton.fields, original_fields);
+        assert_eq!(automaton.previous, original_previous);
+    }
 }